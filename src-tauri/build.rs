@@ -3,6 +3,15 @@ use std::{fs, path::{Path, PathBuf}};
 
 fn main() {
     generate_icons();
+    generate_anim_frames();
+    // Tauri bundles/spawns sidecars under a target-triple-suffixed filename
+    // (e.g. `maude-server-x86_64-unknown-linux-gnu`). `TARGET` is only
+    // available to build scripts, so forward it into the main binary for
+    // the updater to compute the same filename at runtime.
+    println!(
+        "cargo:rustc-env=MAUDE_TARGET_TRIPLE={}",
+        std::env::var("TARGET").expect("TARGET must be set by cargo")
+    );
     tauri_build::build()
 }
 
@@ -108,6 +117,73 @@ fn generate_icons() {
     // cargo tauri build on macOS will handle it via the icon.png fallback.
 }
 
+/// Exports every frame of the `E.png` spritesheet (in sheet order) at the
+/// sizes the startup-icon animation needs, then emits a generated Rust
+/// source file embedding them so `main.rs` can play them back without any
+/// filesystem access at runtime.
+fn generate_anim_frames() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let designs_dir = manifest_dir.parent().unwrap().join("designs");
+    let icons_dir = manifest_dir.join("icons");
+
+    let spritesheet_path = designs_dir.join("E.png");
+    let json_path = designs_dir.join("E.json");
+
+    let sheet = image::open(&spritesheet_path).expect("Failed to open E.png spritesheet");
+    let json_str = fs::read_to_string(&json_path).expect("Failed to read E.json");
+    let json: serde_json::Value = serde_json::from_str(&json_str).expect("Failed to parse E.json");
+
+    let frames = json["frames"].as_object().expect("frames must be an object");
+    // BTreeMap keeps `frame_000`, `frame_001`, ... in sheet order regardless
+    // of the JSON object's own key ordering.
+    let mut ordered: Vec<(&String, &serde_json::Value)> = frames.iter().collect();
+    ordered.sort_by(|a, b| a.0.cmp(b.0));
+
+    let anim_dir = icons_dir.join("anim");
+    let dir_64 = anim_dir.join("64");
+    let dir_256 = anim_dir.join("256");
+    fs::create_dir_all(&dir_64).expect("Failed to create icons/anim/64 dir");
+    fs::create_dir_all(&dir_256).expect("Failed to create icons/anim/256 dir");
+
+    let mut paths_64 = Vec::new();
+    let mut paths_256 = Vec::new();
+
+    for (i, (_, value)) in ordered.iter().enumerate() {
+        let f = &value["frame"];
+        let (x, y, w, h) = (
+            f["x"].as_u64().unwrap() as u32,
+            f["y"].as_u64().unwrap() as u32,
+            f["w"].as_u64().unwrap() as u32,
+            f["h"].as_u64().unwrap() as u32,
+        );
+        let frame = sheet.crop_imm(x, y, w, h);
+
+        let name = format!("frame_{:03}.png", i);
+        let path_64 = dir_64.join(&name);
+        let path_256 = dir_256.join(&name);
+        save_png(&frame, &path_64, 64, FilterType::Nearest);
+        save_png(&frame, &path_256, 256, FilterType::Nearest);
+        paths_64.push(path_64);
+        paths_256.push(path_256);
+    }
+
+    let out_dir = PathBuf::from(env!("OUT_DIR"));
+    let mut generated = String::new();
+    generated.push_str("pub static ICON_ANIM_FRAMES_64: &[&[u8]] = &[\n");
+    for path in &paths_64 {
+        generated.push_str(&format!("    include_bytes!(r{:?}),\n", path));
+    }
+    generated.push_str("];\n\n");
+    generated.push_str("pub static ICON_ANIM_FRAMES_256: &[&[u8]] = &[\n");
+    for path in &paths_256 {
+        generated.push_str(&format!("    include_bytes!(r{:?}),\n", path));
+    }
+    generated.push_str("];\n");
+
+    fs::write(out_dir.join("icon_anim_frames.rs"), generated)
+        .expect("Failed to write icon_anim_frames.rs");
+}
+
 fn save_png(src: &DynamicImage, path: &Path, size: u32, filter: FilterType) {
     let resized = src.resize_exact(size, size, filter);
     resized.save_with_format(path, ImageFormat::Png).unwrap_or_else(|e| {
@@ -0,0 +1,387 @@
+//! Tauri's isolation pattern, applied to the `maude://` proxy boundary.
+//!
+//! The `maude://` origin runs the (potentially compromised) frontend, so it
+//! can't also be where the signing secret lives — anything served on that
+//! origin is readable by a `fetch()` from that same origin's JS, `postMessage`
+//! or not. The secret instead lives behind [`ISOLATION_SCHEME`], a distinct
+//! custom protocol registered on its own in `main.rs`. A different scheme is
+//! a different origin as far as the webview's fetch/XHR same-origin checks
+//! are concerned, so `maude://`'s JS can embed it as an `<iframe>` and talk to
+//! it over `postMessage`, but can't read its response bodies directly. Every
+//! guarded request still has to pass [`authorize`] — checked against a
+//! narrow, named allowlist with per-endpoint method and body-size limits —
+//! before `proxy::handle_request` ever sees it.
+
+use sha2::{Digest, Sha256};
+use tauri::http::{Request, Response, StatusCode};
+
+/// Custom protocol the isolation iframe is served from. Registered as its
+/// own `register_asynchronous_uri_scheme_protocol` in `main.rs`, separate
+/// from `maude` — that's what makes it a different origin instead of just a
+/// different path on the same one.
+pub const ISOLATION_SCHEME: &str = "isolation";
+
+/// Full URL of the isolation iframe's single page. `maude://localhost/`'s
+/// bootstrap script (see [`bootstrap_script`]) embeds this directly as the
+/// `<iframe>`'s `src`.
+const ISOLATION_IFRAME_URL: &str = "isolation://maude/";
+
+/// Backend namespace: any request under this prefix (or `/health`) is meant
+/// for the sidecar and must be signed by the isolation iframe before
+/// `proxy::handle_request` forwards it. This is deliberately broad — it only
+/// decides *whether* a request needs a signature, not which ones can get one;
+/// [`ALLOWLIST`] is the narrow list that actually grants access.
+const BACKEND_PREFIX: &str = "/api/";
+
+/// A single endpoint the isolation iframe is willing to sign, with the
+/// request shapes it's willing to sign for it. Exact-path matches only
+/// (unlike [`BACKEND_PREFIX`], which is a whole-namespace prefix) — adding a
+/// new sidecar endpoint here is an explicit decision, not something that
+/// falls out of it merely living under `/api/`.
+struct GuardedEndpoint {
+    path: &'static str,
+    methods: &'static [&'static str],
+    max_body_bytes: usize,
+}
+
+/// Endpoints the isolation iframe is actually willing to sign, each with its
+/// own allowed methods and body-size ceiling. Genuinely narrower than
+/// `/api/`: a request for an `/api/` endpoint not listed here is rejected
+/// regardless of signature, and a listed endpoint rejects methods or bodies
+/// outside what it declares.
+const ALLOWLIST: &[GuardedEndpoint] = &[
+    GuardedEndpoint {
+        path: "/health",
+        methods: &["GET"],
+        max_body_bytes: 0,
+    },
+    GuardedEndpoint {
+        path: "/api/session",
+        methods: &["GET", "POST"],
+        max_body_bytes: 64 * 1024,
+    },
+];
+
+const SIGNATURE_HEADER: &str = "X-Maude-Isolation-Signature";
+
+/// Per-process secret shared only with the isolation iframe's inline script,
+/// used to sign/verify guarded requests.
+pub struct IsolationSecret(Vec<u8>);
+
+impl IsolationSecret {
+    /// Derives a per-process signing secret from process id + wall clock.
+    /// Not a substitute for a real CSPRNG, but this layer's job is bounding
+    /// an already-same-origin, potentially-compromised frontend script, not
+    /// defeating an attacker with native code execution — good enough for
+    /// that without adding the `rand` crate for one secret.
+    pub fn generate() -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(std::process::id().to_le_bytes());
+        hasher.update(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+                .to_le_bytes(),
+        );
+        Self(hasher.finalize().to_vec())
+    }
+
+    fn sign(&self, method: &str, path: &str, body: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.0);
+        hasher.update(method.as_bytes());
+        hasher.update(path.as_bytes());
+        hasher.update(body);
+        hex_encode(&hasher.finalize())
+    }
+
+    fn secret_hex(&self) -> String {
+        hex_encode(&self.0)
+    }
+}
+
+/// True if `path` is in the backend namespace and therefore must go through
+/// [`authorize`] before reaching the sidecar.
+pub fn is_guarded_path(path: &str) -> bool {
+    path == "/health" || path.starts_with(BACKEND_PREFIX)
+}
+
+fn find_guarded_endpoint(path: &str) -> Option<&'static GuardedEndpoint> {
+    ALLOWLIST.iter().find(|endpoint| endpoint.path == path)
+}
+
+/// Validates a signed, guarded request before it's allowed to reach the
+/// sidecar. Rejects anything off the allowlist, using a method or body size
+/// that endpoint didn't declare, or with a missing/incorrect signature —
+/// only the isolation iframe's inline script, running on a genuinely
+/// separate origin, ever sees the secret needed to produce a valid one.
+pub fn authorize(secret: &IsolationSecret, request: &Request<Vec<u8>>) -> Result<(), Response<Vec<u8>>> {
+    let path = request.uri().path();
+
+    let endpoint = match find_guarded_endpoint(path) {
+        Some(endpoint) => endpoint,
+        None => return Err(reject(StatusCode::FORBIDDEN, "endpoint not on isolation allowlist")),
+    };
+
+    let method = request.method().as_str();
+    if !endpoint.methods.contains(&method) {
+        return Err(reject(StatusCode::FORBIDDEN, "method not allowed for this endpoint"));
+    }
+    if request.body().len() > endpoint.max_body_bytes {
+        return Err(reject(StatusCode::FORBIDDEN, "request body too large for this endpoint"));
+    }
+
+    let provided = request
+        .headers()
+        .get(SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let expected = secret.sign(method, path, request.body());
+    if provided != expected {
+        return Err(reject(
+            StatusCode::FORBIDDEN,
+            "isolation signature missing or invalid",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Serves the isolation iframe itself, with this process's secret and
+/// allowlist inlined directly into the script. Every request on
+/// [`ISOLATION_SCHEME`] gets this same document back — it's a dedicated,
+/// single-page origin, not a path-routed one — so the secret never has to
+/// round-trip through an IPC call or cross onto the `maude://` origin the
+/// loaded page could read from.
+pub fn serve_iframe(secret: &IsolationSecret) -> Response<Vec<u8>> {
+    let html = ISOLATION_IFRAME_TEMPLATE
+        .replace("__MAUDE_SECRET__", &secret.secret_hex())
+        .replace("__MAUDE_ALLOWLIST__", &allowlist_json());
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/html; charset=utf-8")
+        .body(html.into_bytes())
+        .unwrap()
+}
+
+/// The snippet `proxy::handle_request` injects into every HTML response
+/// served on `maude://`: a hidden `<iframe>` pointed at the isolation origin
+/// plus a `window.fetch` wrapper that, for guarded endpoints, round-trips
+/// through the iframe via `postMessage` to get a signature before letting
+/// the real request through. This is what actually makes `authorize`'s
+/// signature check reachable by real frontend code, rather than something
+/// only a client that already knows the protocol could satisfy.
+pub fn bootstrap_script() -> String {
+    BOOTSTRAP_TEMPLATE
+        .replace("__MAUDE_IFRAME_URL__", ISOLATION_IFRAME_URL)
+        .replace("__MAUDE_ALLOWLIST__", &allowlist_json())
+}
+
+fn allowlist_json() -> String {
+    format!(
+        "[{}]",
+        ALLOWLIST
+            .iter()
+            .map(|e| format!("{:?}", e.path))
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+fn reject(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .body(message.as_bytes().to_vec())
+        .unwrap()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Inline script for the isolation iframe. Signs `{method, path, body}` on
+/// request from the main page via `postMessage`, using the secret baked in
+/// above — the main page's fetch wrapper attaches the returned signature as
+/// `X-Maude-Isolation-Signature` before the request ever reaches the proxy.
+/// The allowlist check here is just an early, friendlier rejection for the
+/// frontend; `authorize` above is the one that actually gates the sidecar and
+/// is what matters for security.
+const ISOLATION_IFRAME_TEMPLATE: &str = r#"<!doctype html>
+<script>
+(function () {
+  const SECRET = "__MAUDE_SECRET__";
+  const ALLOWLIST = __MAUDE_ALLOWLIST__;
+
+  function hexToBytes(hex) {
+    const out = new Uint8Array(hex.length / 2);
+    for (let i = 0; i < out.length; i++) out[i] = parseInt(hex.substr(i * 2, 2), 16);
+    return out;
+  }
+
+  function bytesToHex(bytes) {
+    return Array.from(bytes).map((b) => b.toString(16).padStart(2, "0")).join("");
+  }
+
+  async function sign(method, path, body) {
+    const enc = new TextEncoder();
+    const secretBytes = hexToBytes(SECRET);
+    const message = new Uint8Array([
+      ...secretBytes,
+      ...enc.encode(method),
+      ...enc.encode(path),
+      ...enc.encode(body || ""),
+    ]);
+    const digest = await crypto.subtle.digest("SHA-256", message);
+    return bytesToHex(new Uint8Array(digest));
+  }
+
+  window.addEventListener("message", async (event) => {
+    const { id, method, path, body } = event.data || {};
+    if (!id) return;
+
+    if (!ALLOWLIST.includes(path)) {
+      event.source.postMessage({ id, error: "endpoint not allowed" }, event.origin);
+      return;
+    }
+
+    const signature = await sign(method, path, body);
+    event.source.postMessage({ id, signature }, event.origin);
+  });
+})();
+</script>
+"#;
+
+/// Injected into every HTML page served on `maude://`. Mounts the isolation
+/// iframe and replaces `window.fetch` with a wrapper that signs requests to
+/// guarded endpoints through it first. Non-guarded requests (anything not in
+/// `ALLOWLIST`, or cross-origin) pass straight through to the native fetch.
+const BOOTSTRAP_TEMPLATE: &str = r#"<iframe id="__maude_isolation__" src="__MAUDE_IFRAME_URL__" style="display:none"></iframe>
+<script>
+(function () {
+  const ALLOWLIST = __MAUDE_ALLOWLIST__;
+  const iframe = document.getElementById("__maude_isolation__");
+  const iframeOrigin = new URL(iframe.src).origin;
+  const ready = new Promise((resolve) => iframe.addEventListener("load", resolve, { once: true }));
+
+  let nextId = 0;
+  const pending = new Map();
+  window.addEventListener("message", (event) => {
+    if (event.origin !== iframeOrigin) return;
+    const { id, signature, error } = event.data || {};
+    const waiting = pending.get(id);
+    if (!waiting) return;
+    pending.delete(id);
+    if (error) waiting.reject(new Error(error));
+    else waiting.resolve(signature);
+  });
+
+  async function requestSignature(method, path, body) {
+    await ready;
+    return new Promise((resolve, reject) => {
+      const id = ++nextId;
+      pending.set(id, { resolve, reject });
+      iframe.contentWindow.postMessage({ id, method, path, body }, iframeOrigin);
+    });
+  }
+
+  const nativeFetch = window.fetch.bind(window);
+  window.fetch = async function (input, init) {
+    const request = new Request(input, init);
+    const url = new URL(request.url, window.location.href);
+    if (url.origin !== window.location.origin || !ALLOWLIST.includes(url.pathname)) {
+      return nativeFetch(request);
+    }
+
+    const body = init && typeof init.body === "string" ? init.body : "";
+    const signature = await requestSignature(request.method, url.pathname, body);
+    const signed = new Request(request, { headers: new Headers(request.headers) });
+    signed.headers.set("X-Maude-Isolation-Signature", signature);
+    return nativeFetch(signed);
+  };
+})();
+</script>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, body: Vec<u8>) -> Request<Vec<u8>> {
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .body(body)
+            .unwrap()
+    }
+
+    fn signed_request(secret: &IsolationSecret, method: &str, path: &str, body: Vec<u8>) -> Request<Vec<u8>> {
+        let signature = secret.sign(method, path, &body);
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .header(SIGNATURE_HEADER, signature)
+            .body(body)
+            .unwrap()
+    }
+
+    #[test]
+    fn authorize_accepts_a_correctly_signed_allowlisted_request() {
+        let secret = IsolationSecret::generate();
+        let req = signed_request(&secret, "GET", "/health", Vec::new());
+        assert!(authorize(&secret, &req).is_ok());
+    }
+
+    #[test]
+    fn authorize_rejects_endpoints_off_the_allowlist() {
+        let secret = IsolationSecret::generate();
+        let req = signed_request(&secret, "GET", "/api/not-a-real-endpoint", Vec::new());
+        assert!(authorize(&secret, &req).is_err());
+    }
+
+    #[test]
+    fn authorize_rejects_a_method_the_endpoint_did_not_declare() {
+        let secret = IsolationSecret::generate();
+        // /health only declares GET.
+        let req = signed_request(&secret, "POST", "/health", Vec::new());
+        assert!(authorize(&secret, &req).is_err());
+    }
+
+    #[test]
+    fn authorize_rejects_a_body_over_the_endpoints_limit() {
+        let secret = IsolationSecret::generate();
+        let oversized = vec![0u8; 64 * 1024 + 1];
+        let req = signed_request(&secret, "POST", "/api/session", oversized);
+        assert!(authorize(&secret, &req).is_err());
+    }
+
+    #[test]
+    fn authorize_rejects_a_missing_signature() {
+        let secret = IsolationSecret::generate();
+        let req = request("GET", "/health", Vec::new());
+        assert!(authorize(&secret, &req).is_err());
+    }
+
+    #[test]
+    fn authorize_rejects_a_signature_from_a_different_secret() {
+        let secret = IsolationSecret::generate();
+        let other = IsolationSecret::generate();
+        let req = signed_request(&other, "GET", "/health", Vec::new());
+        assert!(authorize(&secret, &req).is_err());
+    }
+
+    #[test]
+    fn authorize_rejects_a_signature_for_a_different_path() {
+        let secret = IsolationSecret::generate();
+        // Signed for /health but presented against /api/session.
+        let signature = secret.sign("GET", "/health", &[]);
+        let req = Request::builder()
+            .method("GET")
+            .uri("/api/session")
+            .header(SIGNATURE_HEADER, signature)
+            .body(Vec::new())
+            .unwrap();
+        assert!(authorize(&secret, &req).is_err());
+    }
+}
@@ -1,121 +1,142 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod isolation;
+mod proxy;
+mod supervisor;
+mod updater;
+
 use tauri::Manager;
 use tauri::WebviewWindowBuilder;
 use tauri::WebviewUrl;
-use tauri_plugin_shell::ShellExt;
+use tauri::image::Image;
+use std::path::PathBuf;
 use std::time::Duration;
 
-fn main() {
-    // Find a free port BEFORE spawning anything.
-    let listener = std::net::TcpListener::bind("127.0.0.1:0")
-        .expect("failed to find a free port");
-    let sidecar_port = listener.local_addr().unwrap().port();
-    drop(listener); // Release port so the sidecar can bind it
+// Generated by build.rs from designs/E.png — one `&[u8]` PNG per sheet frame,
+// at the sizes the tray/window icon needs.
+include!(concat!(env!("OUT_DIR"), "/icon_anim_frames.rs"));
 
-    println!("[maude] selected port {} for sidecar", sidecar_port);
+const ICON_ANIM_FRAME_INTERVAL: Duration = Duration::from_millis(80);
 
+/// Tauri command the frontend's "Check for updates" action invokes directly.
+#[tauri::command]
+async fn check_for_updates(app: tauri::AppHandle) {
+    updater::check_for_updates(app).await;
+}
+
+fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .invoke_handler(tauri::generate_handler![check_for_updates])
         .setup(move |app| {
-            // Create the window pointing to the built frontend initially.
-            // Once the sidecar is healthy, we navigate to the sidecar URL instead.
-            // This makes ALL API requests same-origin — no CORS needed.
-            WebviewWindowBuilder::new(app, "main", WebviewUrl::default())
-            .title("Maude")
-            .inner_size(1200.0, 800.0)
-            .min_inner_size(800.0, 600.0)
-            .build()?;
-
-            let shell = app.shell();
-
             // CARGO_MANIFEST_DIR is src-tauri/ at compile time.
             // Client build lives at ../packages/client/build relative to that.
             let manifest_dir = env!("CARGO_MANIFEST_DIR");
             let client_dist = format!("{}/../packages/client/build", manifest_dir);
+            let client_dist_path = PathBuf::from(&client_dist);
 
-            // Spawn the sidecar with the pre-selected port
-            let (mut rx, child) = shell
-                .sidecar("maude-server")
-                .expect("failed to create maude-server sidecar")
-                .env("PORT", sidecar_port.to_string())
-                .env("CLIENT_DIST", &client_dist)
-                .spawn()
-                .expect("failed to spawn maude-server sidecar");
+            // Secret shared only with the isolation iframe's inline script —
+            // see `isolation::authorize`.
+            app.manage(isolation::IsolationSecret::generate());
 
-            // Store child process for cleanup on exit
-            app.manage(SidecarState {
-                child: std::sync::Mutex::new(Some(child)),
-            });
-
-            // Log sidecar stdout/stderr
-            tauri::async_runtime::spawn(async move {
-                use tauri_plugin_shell::process::CommandEvent;
-                while let Some(event) = rx.recv().await {
-                    match event {
-                        CommandEvent::Stdout(line) => {
-                            println!("[maude-server] {}", String::from_utf8_lossy(&line));
-                        }
-                        CommandEvent::Stderr(line) => {
-                            eprintln!("[maude-server] {}", String::from_utf8_lossy(&line));
-                        }
-                        CommandEvent::Terminated(status) => {
-                            eprintln!("[maude-server] terminated: {:?}", status);
-                            break;
-                        }
-                        CommandEvent::Error(err) => {
-                            eprintln!("[maude-server] error: {}", err);
-                            break;
+            // Load `maude://localhost/` up front instead of the bundled
+            // frontend with a later `window.location.href` redirect to a
+            // guessed TCP port. The `maude` protocol handler below proxies
+            // to the sidecar over a Unix domain socket once it's healthy,
+            // and falls back to serving `client_dist` directly until then —
+            // so the window is never blank and never leaves same-origin.
+            let protocol_app_handle = app.handle().clone();
+            WebviewWindowBuilder::new(
+                app,
+                "main",
+                WebviewUrl::External("maude://localhost/".parse().expect("valid maude:// url")),
+            )
+            .title("Maude")
+            .inner_size(1200.0, 800.0)
+            .min_inner_size(800.0, 600.0)
+            .register_asynchronous_uri_scheme_protocol("maude", move |request, responder| {
+                let app_handle = protocol_app_handle.clone();
+                let client_dist = client_dist_path.clone();
+                let request = request.map(|body| body.to_vec());
+                tauri::async_runtime::spawn(async move {
+                    if isolation::is_guarded_path(request.uri().path()) {
+                        let secret = app_handle.state::<isolation::IsolationSecret>();
+                        if let Err(rejection) = isolation::authorize(&secret, &request) {
+                            responder.respond(rejection);
+                            return;
                         }
-                        _ => {}
                     }
-                }
-            });
 
-            // Poll health; when ready, navigate the webview to the sidecar URL.
-            // This makes the page same-origin with the API — no CORS needed.
-            let app_handle = app.handle().clone();
-            tauri::async_runtime::spawn(async move {
-                let client = reqwest::Client::new();
-                let health_url = format!("http://localhost:{}/health", sidecar_port);
+                    let socket_path = app_handle
+                        .try_state::<supervisor::SidecarState>()
+                        .and_then(|state| state.ready_socket_path());
+                    let response = proxy::handle_request(socket_path, &client_dist, request).await;
+                    responder.respond(response);
+                });
+            })
+            // Genuinely separate origin from `maude://` — see `isolation`'s
+            // module doc. Every request here gets the same iframe document
+            // back; there's nothing else on this origin to route to.
+            .register_asynchronous_uri_scheme_protocol(isolation::ISOLATION_SCHEME, {
+                let app_handle = app.handle().clone();
+                move |_request, responder| {
+                    let secret = app_handle.state::<isolation::IsolationSecret>();
+                    responder.respond(isolation::serve_iframe(&secret));
+                }
+            })
+            .build()?;
 
-                for _ in 0..60 {
-                    tokio::time::sleep(Duration::from_millis(250)).await;
-                    if let Ok(resp) = client.get(&health_url).send().await {
-                        if resp.status().is_success() {
-                            println!("[maude] server ready on port {}", sidecar_port);
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.eval(&format!(
-                                    "window.location.href = 'http://localhost:{}/';",
-                                    sidecar_port
-                                ));
-                            }
-                            return;
-                        }
+            // Play the startup spritesheet on the window icon while the sidecar
+            // boots, so users see motion instead of a static icon and a blank
+            // window. The supervisor stops this and freezes the final frame
+            // once the first sidecar instance reports healthy.
+            let anim_app_handle = app.handle().clone();
+            let anim_running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+            let anim_running_handle = anim_running.clone();
+            let anim_task = tauri::async_runtime::spawn(async move {
+                let frames: Vec<Image> = ICON_ANIM_FRAMES_64
+                    .iter()
+                    .map(|bytes| Image::from_bytes(bytes).expect("failed to decode startup icon frame"))
+                    .collect();
+                let mut i = 0usize;
+                while anim_running_handle.load(std::sync::atomic::Ordering::Relaxed) {
+                    if let Some(window) = anim_app_handle.get_webview_window("main") {
+                        let _ = window.set_icon(frames[i % frames.len()].clone());
                     }
+                    i += 1;
+                    tokio::time::sleep(ICON_ANIM_FRAME_INTERVAL).await;
                 }
-                eprintln!("[maude] server failed to start within 15 seconds");
+            });
+            let final_frame = Image::from_bytes(
+                ICON_ANIM_FRAMES_64.last().expect("no startup icon frames"),
+            )
+            .expect("failed to decode final startup icon frame");
+
+            supervisor::start(
+                app.handle(),
+                client_dist,
+                supervisor::FirstBootIcon::new(anim_running, anim_task, final_frame),
+            );
+
+            // Check for shell/sidecar updates once on startup, in addition to
+            // the manual "Check for updates" command.
+            let update_app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                updater::check_for_updates(update_app_handle).await;
             });
 
             Ok(())
         })
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::Destroyed = event {
-                if let Some(state) = window.try_state::<SidecarState>() {
-                    if let Ok(mut guard) = state.child.lock() {
-                        if let Some(child) = guard.take() {
-                            let _ = child.kill();
-                        }
-                    }
+                if let Some(state) = window.try_state::<supervisor::SidecarState>() {
+                    state.kill_current();
                 }
             }
         })
         .run(tauri::generate_context!())
         .expect("error while running Maude");
 }
-
-struct SidecarState {
-    child: std::sync::Mutex<Option<tauri_plugin_shell::process::CommandChild>>,
-}
@@ -0,0 +1,299 @@
+//! Backs the `maude://` custom protocol: the webview loads `maude://localhost/`
+//! up front and every request — page load included — comes through here.
+//!
+//! This replaces the old `127.0.0.1:0`-bind-then-drop dance (a genuine TOCTOU
+//! race against whatever else might grab the released port) with a proxy
+//! over a local IPC channel that only the sidecar we spawned ever listens
+//! on — a Unix domain socket on Unix, a named pipe on Windows (`connect_sidecar`
+//! picks the right one; `supervisor::pick_socket_path` names it). While the
+//! sidecar isn't up yet (or is mid-restart) requests fall back to serving
+//! the bundled client files directly, so the window never shows a blank page
+//! waiting on the backend.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tauri::http::{Request, Response, StatusCode};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Blanket-implemented marker for whichever concrete stream type
+/// `connect_sidecar` hands back, so `forward_to_sidecar` doesn't need to know
+/// whether it's talking to a Unix domain socket or a Windows named pipe.
+trait SidecarStream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> SidecarStream for T {}
+
+/// Connects to the sidecar's `socket_path` over whatever IPC transport this
+/// platform uses for it — a Unix domain socket, same as `supervisor::pick_socket_path`
+/// picks for this platform.
+#[cfg(unix)]
+async fn connect_sidecar(socket_path: &Path) -> io::Result<Box<dyn SidecarStream>> {
+    Ok(Box::new(tokio::net::UnixStream::connect(socket_path).await?))
+}
+
+/// Windows has no Unix domain sockets, so the sidecar listens on a named
+/// pipe instead (see `supervisor::pick_socket_path`). The pipe may not have
+/// finished being created yet the instant we try to connect, which shows up
+/// as `ERROR_PIPE_BUSY` — retry briefly rather than failing the whole
+/// request, the same way a Unix connect would just succeed once the backing
+/// socket file exists.
+#[cfg(windows)]
+async fn connect_sidecar(socket_path: &Path) -> io::Result<Box<dyn SidecarStream>> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    const ERROR_PIPE_BUSY: i32 = 231;
+    let pipe_name = socket_path.to_string_lossy();
+
+    loop {
+        match ClientOptions::new().open(pipe_name.as_ref()) {
+            Ok(client) => return Ok(Box::new(client)),
+            Err(err) if err.raw_os_error() == Some(ERROR_PIPE_BUSY) => {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Handles one `maude://` request: forwards it to the sidecar over
+/// `socket_path` if one is given, otherwise serves straight from
+/// `client_dist`. Also falls back to `client_dist` if the forward fails,
+/// since a socket can go stale between the caller reading it and us
+/// connecting (the sidecar crashed a moment ago).
+pub async fn handle_request(
+    socket_path: Option<PathBuf>,
+    client_dist: &Path,
+    request: Request<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    let response = match socket_path {
+        Some(socket_path) => match forward_to_sidecar(&socket_path, &request).await {
+            Ok(response) => response,
+            Err(err) => {
+                eprintln!("[maude] proxy: sidecar forward failed, serving bundled client ({})", err);
+                serve_static(client_dist, request.uri().path())
+            }
+        },
+        None => serve_static(client_dist, request.uri().path()),
+    };
+    inject_isolation_bootstrap(response)
+}
+
+/// Mounts the isolation iframe and fetch shim (see `isolation::bootstrap_script`)
+/// into every HTML document this proxy serves on `maude://`, regardless of
+/// whether it came from the sidecar or the static fallback — this is the one
+/// funnel point both paths go through, so it's the only place that needs to
+/// know about the isolation boundary at all.
+fn inject_isolation_bootstrap(response: Response<Vec<u8>>) -> Response<Vec<u8>> {
+    let is_html = response
+        .headers()
+        .get(tauri::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("text/html"));
+    if !is_html {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let mut html = String::from_utf8_lossy(&body).into_owned();
+    let bootstrap = crate::isolation::bootstrap_script();
+    match html.rfind("</body>") {
+        Some(pos) => html.insert_str(pos, &bootstrap),
+        None => html.push_str(&bootstrap),
+    }
+    parts.headers.remove(tauri::http::header::CONTENT_LENGTH);
+    Response::from_parts(parts, html.into_bytes())
+}
+
+/// Plain GET /health over the socket, used by the supervisor's health poll.
+pub async fn is_healthy(socket_path: &Path) -> bool {
+    let request = Request::builder()
+        .method("GET")
+        .uri("/health")
+        .body(Vec::new())
+        .expect("well-formed health request");
+    matches!(
+        forward_to_sidecar(socket_path, &request).await,
+        Ok(response) if response.status().is_success()
+    )
+}
+
+async fn forward_to_sidecar(
+    socket_path: &Path,
+    request: &Request<Vec<u8>>,
+) -> io::Result<Response<Vec<u8>>> {
+    let mut stream = connect_sidecar(socket_path).await?;
+
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n",
+        request.method(),
+        path,
+        request.body().len(),
+    );
+    for (name, value) in request.headers() {
+        if name == tauri::http::header::HOST || name == tauri::http::header::CONNECTION {
+            continue;
+        }
+        if let Ok(value) = value.to_str() {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(request.body()).await?;
+    stream.shutdown().await?;
+
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw).await?;
+    parse_http_response(&raw)
+}
+
+/// Hand-rolled HTTP/1.1 response parsing. The sidecar is our own binary
+/// speaking a small internal protocol, so we don't need a general-purpose
+/// client (no chunked transfer-encoding, no redirects) — just enough to
+/// round-trip its responses through the webview.
+fn parse_http_response(raw: &[u8]) -> io::Result<Response<Vec<u8>>> {
+    let header_end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| i + 4)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed sidecar response"))?;
+    let head = std::str::from_utf8(&raw[..header_end])
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "non-utf8 response head"))?;
+    let body = raw[header_end..].to_vec();
+
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .unwrap_or(502);
+
+    let mut builder =
+        Response::builder().status(StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_GATEWAY));
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            builder = builder.header(name.trim(), value.trim());
+        }
+    }
+
+    builder
+        .body(body)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+fn serve_static(client_dist: &Path, request_path: &str) -> Response<Vec<u8>> {
+    let relative = request_path.trim_start_matches('/');
+    let file_path = safe_join(client_dist, relative)
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| client_dist.join("index.html"));
+
+    match std::fs::read(&file_path) {
+        Ok(bytes) => Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", guess_mime(&file_path))
+            .body(bytes)
+            .unwrap(),
+        Err(err) => {
+            eprintln!("[maude] proxy: failed to serve {}: {}", file_path.display(), err);
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Vec::new())
+                .unwrap()
+        }
+    }
+}
+
+/// Joins `relative` onto `base`, rejecting `..`/root/prefix components so a
+/// request path can't escape `client_dist` (e.g.
+/// `maude://localhost/../../../../etc/passwd`). Works without the target
+/// existing, unlike canonicalizing-and-checking-the-prefix, which would let
+/// a missing-file request bypass the check entirely.
+fn safe_join(base: &Path, relative: &str) -> Option<PathBuf> {
+    let mut path = base.to_path_buf();
+    for component in Path::new(relative).components() {
+        match component {
+            std::path::Component::Normal(part) => path.push(part),
+            std::path::Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+fn guess_mime(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_join_rejects_parent_traversal() {
+        let base = Path::new("/client/dist");
+        assert_eq!(safe_join(base, "../../../../etc/passwd"), None);
+        assert_eq!(safe_join(base, "a/../../b"), None);
+    }
+
+    #[test]
+    fn safe_join_rejects_absolute_paths() {
+        let base = Path::new("/client/dist");
+        assert_eq!(safe_join(base, "/etc/passwd"), None);
+    }
+
+    #[test]
+    fn safe_join_accepts_plain_relative_paths() {
+        let base = Path::new("/client/dist");
+        assert_eq!(
+            safe_join(base, "assets/app.js"),
+            Some(PathBuf::from("/client/dist/assets/app.js"))
+        );
+        // `.` segments are harmless and should just be skipped.
+        assert_eq!(
+            safe_join(base, "./assets/./app.js"),
+            Some(PathBuf::from("/client/dist/assets/app.js"))
+        );
+    }
+
+    #[test]
+    fn parse_http_response_reads_status_and_headers() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nhello";
+        let response = parse_http_response(raw).expect("well-formed response");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "text/plain"
+        );
+        assert_eq!(response.body(), b"hello");
+    }
+
+    #[test]
+    fn parse_http_response_rejects_missing_header_terminator() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain";
+        assert!(parse_http_response(raw).is_err());
+    }
+
+    #[test]
+    fn parse_http_response_falls_back_to_bad_gateway_on_unparsable_status() {
+        let raw = b"garbage status line\r\n\r\n";
+        let response = parse_http_response(raw).expect("still parses, just with a fallback status");
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+}
@@ -0,0 +1,336 @@
+//! Supervises the `maude-server` sidecar: spawns it, watches for abnormal
+//! exit, and respawns it with a fresh Unix domain socket and exponential
+//! backoff so a crashed sidecar doesn't leave the user staring at a dead
+//! window.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tauri::image::Image;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use tauri_plugin_shell::ShellExt;
+use tokio::sync::oneshot;
+
+use crate::proxy;
+
+pub(crate) const SIDECAR_NAME: &str = "maude-server";
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const HEALTH_POLL_ATTEMPTS: u32 = 60; // ~15s
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+const MAX_RESTARTS: u32 = 20;
+/// Once the sidecar has stayed up this long, a later crash is treated as a
+/// fresh failure rather than a continuation of the same crash loop.
+const STABLE_UPTIME_RESET: Duration = Duration::from_secs(30);
+
+const RECONNECTING_HTML: &str = "<!doctype html><html><body style=\"font:14px -apple-system,sans-serif;display:flex;height:100vh;align-items:center;justify-content:center;margin:0;background:#111;color:#eee\"><p>Maude lost its connection to the server and is reconnecting&hellip;</p></body></html>";
+
+/// Holds the currently-running sidecar child process and, once it's passed
+/// its health check, the socket the `maude://` proxy should forward to.
+/// Updated on every restart so the `Destroyed` window-event cleanup always
+/// kills the live child, never a stale handle from a previous spawn, and the
+/// proxy never forwards to a socket nobody is listening on anymore.
+pub struct SidecarState {
+    child: Mutex<Option<CommandChild>>,
+    ready_socket: Mutex<Option<PathBuf>>,
+}
+
+impl SidecarState {
+    pub fn kill_current(&self) {
+        *self.ready_socket.lock().unwrap() = None;
+        if let Ok(mut guard) = self.child.lock() {
+            if let Some(child) = guard.take() {
+                let _ = child.kill();
+            }
+        }
+    }
+
+    /// The socket the proxy should forward `maude://` requests to, if the
+    /// sidecar is currently up and has passed its health check.
+    pub fn ready_socket_path(&self) -> Option<PathBuf> {
+        self.ready_socket.lock().unwrap().clone()
+    }
+
+    fn set_child(&self, child: CommandChild) {
+        *self.child.lock().unwrap() = Some(child);
+    }
+
+    fn mark_ready(&self, socket_path: PathBuf) {
+        *self.ready_socket.lock().unwrap() = Some(socket_path);
+    }
+}
+
+/// The startup icon animation spawned by `main.rs` for the very first boot.
+/// The supervisor stops it and freezes the window icon on `final_frame` the
+/// first time the sidecar reports healthy; restarts after that show the
+/// reconnecting page instead.
+pub struct FirstBootIcon {
+    running: Arc<AtomicBool>,
+    task: tauri::async_runtime::JoinHandle<()>,
+    final_frame: Image<'static>,
+}
+
+impl FirstBootIcon {
+    pub fn new(
+        running: Arc<AtomicBool>,
+        task: tauri::async_runtime::JoinHandle<()>,
+        final_frame: Image<'static>,
+    ) -> Self {
+        Self {
+            running,
+            task,
+            final_frame,
+        }
+    }
+
+    fn stop(self, app: &AppHandle) {
+        self.running.store(false, Ordering::Relaxed);
+        self.task.abort();
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.set_icon(self.final_frame);
+        }
+    }
+}
+
+/// Kills the currently-running sidecar so the supervisor loop's crash path
+/// picks it up and respawns the freshly-updated binary on a new port. Used
+/// by the updater once it has atomically replaced the sidecar executable.
+pub fn restart_now(app: &AppHandle) {
+    if let Some(state) = app.try_state::<SidecarState>() {
+        state.kill_current();
+    }
+}
+
+/// Starts the supervisor loop in the background. `client_dist` and
+/// `boot_icon` are only used for the very first spawn; every restart after
+/// that re-selects a free port, respawns the sidecar, and re-navigates the
+/// webview once the new instance is healthy.
+pub fn start(app: &AppHandle, client_dist: String, boot_icon: FirstBootIcon) {
+    app.manage(SidecarState {
+        child: Mutex::new(None),
+        ready_socket: Mutex::new(None),
+    });
+
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        run(app_handle, client_dist, boot_icon).await;
+    });
+}
+
+async fn run(app: AppHandle, client_dist: String, boot_icon: FirstBootIcon) {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut restarts = 0u32;
+    let mut boot_icon = Some(boot_icon);
+
+    loop {
+        let socket_path = pick_socket_path();
+        println!("[maude] selected socket {} for sidecar", socket_path.display());
+
+        if boot_icon.is_none() {
+            // This is a restart, not the initial boot — let the user know.
+            show_reconnecting_page(&app);
+        }
+
+        let (rx, child) = match spawn_sidecar(&app, &socket_path, &client_dist) {
+            Ok(pair) => pair,
+            Err(err) => {
+                eprintln!("[maude] failed to spawn maude-server sidecar: {}", err);
+                if !wait_before_retry(&mut backoff, &mut restarts).await {
+                    stop_boot_icon(&app, &mut boot_icon);
+                    return;
+                }
+                continue;
+            }
+        };
+
+        let state = app.state::<SidecarState>();
+        state.set_child(child);
+
+        let (crash_tx, mut crash_rx) = oneshot::channel();
+        tauri::async_runtime::spawn(watch_process(rx, crash_tx));
+
+        let started_at = Instant::now();
+        let became_healthy = tokio::select! {
+            healthy = poll_health(&socket_path) => healthy,
+            _ = &mut crash_rx => false,
+        };
+
+        // The startup animation only covers the very first boot attempt's
+        // health wait. Stop it here unconditionally — whether that attempt
+        // succeeded, timed out, or crashed before ever answering a health
+        // check — so it never outlives the attempt it was meant to cover.
+        // After the first time through the loop `boot_icon` is already
+        // `None` and this is a no-op.
+        stop_boot_icon(&app, &mut boot_icon);
+
+        if became_healthy {
+            println!("[maude] server ready on socket {}", socket_path.display());
+            state.mark_ready(socket_path.clone());
+
+            // Sidecar is up; stay here until it eventually goes down, then
+            // fall through to the restart path below.
+            let _ = (&mut crash_rx).await;
+        } else {
+            eprintln!(
+                "[maude] sidecar on socket {} did not become healthy in time",
+                socket_path.display()
+            );
+        }
+
+        state.kill_current();
+        cleanup_socket_path(&socket_path);
+
+        if started_at.elapsed() >= STABLE_UPTIME_RESET {
+            backoff = INITIAL_BACKOFF;
+            restarts = 0;
+        }
+
+        if !wait_before_retry(&mut backoff, &mut restarts).await {
+            return;
+        }
+    }
+}
+
+/// Stops and aborts the startup icon animation if it's still running. A
+/// no-op once it's already been taken, so it's safe to call from every exit
+/// path of the first boot attempt instead of just the success case.
+fn stop_boot_icon(app: &AppHandle, boot_icon: &mut Option<FirstBootIcon>) {
+    if let Some(icon) = boot_icon.take() {
+        icon.stop(app);
+    }
+}
+
+static NEXT_SOCKET_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Picks a fresh Unix domain socket path under the OS temp dir, unique to
+/// this process and spawn attempt, and clears out any stale file left behind
+/// by a socket of the same name from a previous crash.
+#[cfg(unix)]
+fn pick_socket_path() -> PathBuf {
+    let id = NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("maude-{}-{}.sock", std::process::id(), id));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Picks a fresh named pipe path, Windows' analogue of a Unix domain socket
+/// for this purpose — process-local, no TCP port to guess or race. Pipe
+/// names live in the `\\.\pipe\` namespace rather than the filesystem, so
+/// unlike the Unix path there's no stale file to clear first.
+#[cfg(windows)]
+fn pick_socket_path() -> PathBuf {
+    let id = NEXT_SOCKET_ID.fetch_add(1, Ordering::Relaxed);
+    PathBuf::from(format!(r"\\.\pipe\maude-{}-{}", std::process::id(), id))
+}
+
+/// Removes the on-disk socket file left behind by a Unix domain socket.
+/// Named pipes have no filesystem entry to clean up, so this is a no-op on
+/// Windows.
+#[cfg(unix)]
+fn cleanup_socket_path(path: &std::path::Path) {
+    let _ = std::fs::remove_file(path);
+}
+
+#[cfg(windows)]
+fn cleanup_socket_path(_path: &std::path::Path) {}
+
+fn spawn_sidecar(
+    app: &AppHandle,
+    socket_path: &std::path::Path,
+    client_dist: &str,
+) -> tauri_plugin_shell::Result<(tokio::sync::mpsc::Receiver<CommandEvent>, CommandChild)> {
+    app.shell()
+        .sidecar(SIDECAR_NAME)?
+        .env("SOCKET_PATH", socket_path.display().to_string())
+        .env("CLIENT_DIST", client_dist)
+        .spawn()
+}
+
+/// Forwards stdout/stderr to the console and signals `crash_tx` the moment
+/// the sidecar exits abnormally.
+async fn watch_process(
+    mut rx: tokio::sync::mpsc::Receiver<CommandEvent>,
+    crash_tx: oneshot::Sender<()>,
+) {
+    let mut crash_tx = Some(crash_tx);
+    while let Some(event) = rx.recv().await {
+        match event {
+            CommandEvent::Stdout(line) => {
+                println!("[maude-server] {}", String::from_utf8_lossy(&line));
+            }
+            CommandEvent::Stderr(line) => {
+                eprintln!("[maude-server] {}", String::from_utf8_lossy(&line));
+            }
+            CommandEvent::Terminated(status) => {
+                eprintln!("[maude-server] terminated: {:?}", status);
+                if let Some(tx) = crash_tx.take() {
+                    let _ = tx.send(());
+                }
+                break;
+            }
+            CommandEvent::Error(err) => {
+                eprintln!("[maude-server] error: {}", err);
+                if let Some(tx) = crash_tx.take() {
+                    let _ = tx.send(());
+                }
+                break;
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn poll_health(socket_path: &std::path::Path) -> bool {
+    for _ in 0..HEALTH_POLL_ATTEMPTS {
+        tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+        if proxy::is_healthy(socket_path).await {
+            return true;
+        }
+    }
+    false
+}
+
+fn show_reconnecting_page(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.eval(&format!(
+            "document.open(); document.write({}); document.close();",
+            js_string_literal(RECONNECTING_HTML)
+        ));
+    }
+}
+
+/// Minimal JS string-literal escaping, just enough for the embedded
+/// reconnecting page (no external dependency needed for this one call site).
+fn js_string_literal(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Sleeps for the current backoff, doubling it (capped) and counting the
+/// attempt. Returns `false` once `MAX_RESTARTS` has been exhausted.
+async fn wait_before_retry(backoff: &mut Duration, restarts: &mut u32) -> bool {
+    *restarts += 1;
+    if *restarts > MAX_RESTARTS {
+        eprintln!(
+            "[maude] sidecar failed {} times in a row, giving up",
+            MAX_RESTARTS
+        );
+        return false;
+    }
+    tokio::time::sleep(*backoff).await;
+    *backoff = (*backoff * 2).min(MAX_BACKOFF);
+    true
+}
@@ -0,0 +1,236 @@
+//! Self-update channel for the Maude shell and the `maude-server` sidecar.
+//!
+//! The shell follows Tauri's standard updater flow (`tauri-plugin-updater`):
+//! download, verify, install, relaunch. The sidecar ships as an external
+//! binary spawned through `shell.sidecar(...)`, so it needs its own verified
+//! update path independent of the webview/app shell: download the signed
+//! binary, verify it against the embedded release public key, atomically
+//! replace the bundled binary, then ask the supervisor to respawn it.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_updater::UpdaterExt;
+
+use crate::supervisor;
+
+/// Embedded release-signing public key (ed25519). Generated offline and
+/// pinned here; rotate alongside `keys/sidecar_release.pub` if the signing
+/// key is ever rotated.
+const SIDECAR_RELEASE_PUBLIC_KEY: &[u8; 32] = include_bytes!("../keys/sidecar_release.pub");
+
+const SIDECAR_CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Target triple the running binary was built for, forwarded from `build.rs`
+/// (only build scripts see `TARGET`). Tauri suffixes bundled sidecar
+/// binaries with this same triple, so the updater has to match it exactly
+/// to find (and replace) the file `shell.sidecar(...)` actually spawns.
+const TARGET_TRIPLE: &str = env!("MAUDE_TARGET_TRIPLE");
+
+#[derive(Debug, Deserialize)]
+struct ReleaseManifest {
+    sidecar_version: String,
+    sidecar_url: String,
+    /// Hex-encoded SHA-256 of the downloaded binary.
+    sidecar_sha256: String,
+    /// Hex-encoded ed25519 signature over the downloaded binary's raw bytes.
+    sidecar_signature: String,
+}
+
+#[derive(Debug)]
+enum UpdateError {
+    Network(String),
+    Verification(String),
+    Io(String),
+}
+
+impl std::fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateError::Network(msg) => write!(f, "network error: {}", msg),
+            UpdateError::Verification(msg) => write!(f, "verification failed: {}", msg),
+            UpdateError::Io(msg) => write!(f, "io error: {}", msg),
+        }
+    }
+}
+
+impl From<reqwest::Error> for UpdateError {
+    fn from(err: reqwest::Error) -> Self {
+        UpdateError::Network(err.to_string())
+    }
+}
+
+impl From<std::io::Error> for UpdateError {
+    fn from(err: std::io::Error) -> Self {
+        UpdateError::Io(err.to_string())
+    }
+}
+
+/// Reads the configured release endpoint. Returns `None` when unset, in
+/// which case update checks are skipped rather than guessing a URL.
+fn release_endpoint() -> Option<String> {
+    std::env::var("MAUDE_UPDATE_ENDPOINT").ok()
+}
+
+/// Runs once at startup and whenever the user triggers "Check for updates":
+/// checks the shell via the standard Tauri updater, then checks/installs a
+/// newer sidecar binary if the configured release endpoint advertises one.
+pub async fn check_for_updates(app: AppHandle) {
+    check_shell_update(&app).await;
+
+    let Some(endpoint) = release_endpoint() else {
+        println!("[maude] no update endpoint configured (MAUDE_UPDATE_ENDPOINT unset), skipping sidecar update check");
+        return;
+    };
+
+    if let Err(err) = check_sidecar_update(&app, &endpoint).await {
+        eprintln!("[maude] sidecar update check failed: {}", err);
+    }
+}
+
+async fn check_shell_update(app: &AppHandle) {
+    let updater = match app.updater() {
+        Ok(updater) => updater,
+        Err(err) => {
+            eprintln!("[maude] updater plugin unavailable: {}", err);
+            return;
+        }
+    };
+
+    match updater.check().await {
+        Ok(Some(update)) => {
+            println!("[maude] shell update {} available, downloading", update.version);
+            if let Err(err) = update.download_and_install(|_, _| {}, || {}).await {
+                eprintln!("[maude] failed to install shell update: {}", err);
+                return;
+            }
+            app.restart();
+        }
+        Ok(None) => println!("[maude] shell is up to date"),
+        Err(err) => eprintln!("[maude] shell update check failed: {}", err),
+    }
+}
+
+async fn check_sidecar_update(app: &AppHandle, endpoint: &str) -> Result<(), UpdateError> {
+    let manifest: ReleaseManifest = reqwest::get(endpoint).await?.json().await?;
+
+    if manifest.sidecar_version == SIDECAR_CURRENT_VERSION {
+        println!("[maude] sidecar is up to date ({})", SIDECAR_CURRENT_VERSION);
+        return Ok(());
+    }
+
+    println!(
+        "[maude] sidecar update {} -> {} available, downloading",
+        SIDECAR_CURRENT_VERSION, manifest.sidecar_version
+    );
+
+    let bytes = reqwest::get(&manifest.sidecar_url).await?.bytes().await?;
+    verify_checksum(&bytes, &manifest.sidecar_sha256)?;
+    verify_signature(&bytes, &manifest.sidecar_signature)?;
+
+    let sidecar_path = resolve_sidecar_path(app)?;
+    replace_binary_atomically(&sidecar_path, &bytes)?;
+
+    println!(
+        "[maude] sidecar updated to {}, restarting",
+        manifest.sidecar_version
+    );
+    supervisor::restart_now(app);
+
+    Ok(())
+}
+
+fn verify_checksum(bytes: &[u8], expected_hex: &str) -> Result<(), UpdateError> {
+    let digest = Sha256::digest(bytes);
+    if hex_encode(&digest) != expected_hex.to_lowercase() {
+        return Err(UpdateError::Verification(
+            "sidecar checksum mismatch".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn verify_signature(bytes: &[u8], signature_hex: &str) -> Result<(), UpdateError> {
+    let sig_bytes = hex_decode(signature_hex)
+        .map_err(|_| UpdateError::Verification("malformed signature encoding".into()))?;
+    let signature = Signature::from_slice(&sig_bytes)
+        .map_err(|_| UpdateError::Verification("malformed signature".into()))?;
+    let key = VerifyingKey::from_bytes(SIDECAR_RELEASE_PUBLIC_KEY)
+        .map_err(|_| UpdateError::Verification("invalid embedded release public key".into()))?;
+    key.verify(bytes, &signature)
+        .map_err(|_| UpdateError::Verification("sidecar signature verification failed".into()))
+}
+
+/// Locates the bundled sidecar binary on disk. Tauri doesn't place
+/// `externalBin` sidecars in the same directory on every platform/bundle
+/// format, so this checks the candidates actually in use (resource dir,
+/// and alongside the main executable) rather than assuming one.
+fn resolve_sidecar_path(app: &AppHandle) -> Result<PathBuf, UpdateError> {
+    let filename = sidecar_binary_name();
+
+    let candidates = [
+        app.path().resource_dir().ok().map(|dir| dir.join(&filename)),
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join(&filename))),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            UpdateError::Io(format!(
+                "could not locate bundled sidecar binary {} in resource dir or next to the executable",
+                filename
+            ))
+        })
+}
+
+fn sidecar_binary_name() -> String {
+    #[cfg(target_os = "windows")]
+    {
+        format!("{}-{}.exe", supervisor::SIDECAR_NAME, TARGET_TRIPLE)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        format!("{}-{}", supervisor::SIDECAR_NAME, TARGET_TRIPLE)
+    }
+}
+
+/// Writes the new binary alongside the old one and renames it into place —
+/// a rename is atomic on the same filesystem, so the supervisor never sees a
+/// partially-written sidecar executable.
+fn replace_binary_atomically(path: &Path, bytes: &[u8]) -> Result<(), UpdateError> {
+    let tmp_path = path.with_extension("new");
+    {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(s: &str) -> Result<Vec<u8>, ()> {
+    if s.len() % 2 != 0 {
+        return Err(());
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| ()))
+        .collect()
+}